@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::isa;
+use crate::{Instruction, Opcode};
+
+// Errors produced while assembling a program, each carrying the 1-based
+// source line it came from so a user can jump straight to the problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownMnemonic { line: usize, mnemonic: String },
+    InvalidOperand { line: usize, token: String },
+    WrongOperandCount { line: usize, mnemonic: String, expected: usize, found: usize },
+    UndefinedLabel { line: usize, label: String },
+    DuplicateLabel { line: usize, label: String },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {}: unknown mnemonic '{}'", line, mnemonic)
+            }
+            AsmError::InvalidOperand { line, token } => {
+                write!(f, "line {}: invalid operand '{}'", line, token)
+            }
+            AsmError::WrongOperandCount { line, mnemonic, expected, found } => write!(
+                f,
+                "line {}: '{}' expects {} operand(s), found {}",
+                line, mnemonic, expected, found
+            ),
+            AsmError::UndefinedLabel { line, label } => {
+                write!(f, "line {}: undefined label '{}'", line, label)
+            }
+            AsmError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label '{}' already defined", line, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+// The operand shape a mnemonic expects, used to both validate operand count
+// and know how to parse each token. Also reused by `disassemble` to know which
+// operands are meaningful for a given opcode.
+pub(crate) enum Shape {
+    None,
+    Reg,
+    RegReg,
+    RegRegReg,
+    RegAddr,
+    RegImm,
+    Label,
+    RegLabel,
+    RegRegLabel,
+}
+
+fn mnemonic_to_opcode(mnemonic: &str) -> Option<Opcode> {
+    use Opcode::*;
+    Some(match mnemonic {
+        "nop" => Nop,
+        "add" => Add,
+        "sub" => Sub,
+        "mul" => Mul,
+        "div" => Div,
+        "store" => Store,
+        "load" => Load,
+        "li" => LoadImmediate,
+        "push" => Push,
+        "pop" => Pop,
+        "jmp" => Jmp,
+        "jz" => Jz,
+        "jnz" => Jnz,
+        "mov" => Mov,
+        "je" => Je,
+        "jne" => Jne,
+        "and" => And,
+        "or" => Or,
+        "xor" => Xor,
+        "not" => Not,
+        "shl" => Shl,
+        "shr" => Shr,
+        "cmp" => Cmp,
+        "test" => Test,
+        "b" => B,
+        "bz" => Bz,
+        "bnz" => Bnz,
+        "neg" => Neg,
+        "abs" => Abs,
+        "mod" => Mod,
+        "inc" => Inc,
+        "dec" => Dec,
+        "halt" => Halt,
+        "bcc" => Bcc,
+        "bcs" => Bcs,
+        "bge" => Bge,
+        "blt" => Blt,
+        "bgt" => Bgt,
+        "ble" => Ble,
+        "addu" => AddU,
+        "adds" => AddS,
+        "addf" => AddF,
+        "subu" => SubU,
+        "subs" => SubS,
+        "subf" => SubF,
+        "mulu" => MulU,
+        "muls" => MulS,
+        "mulf" => MulF,
+        "divu" => DivU,
+        "divs" => DivS,
+        "divf" => DivF,
+        "modu" => ModU,
+        "mods" => ModS,
+        "modf" => ModF,
+        "shru" => ShrU,
+        _ => return None,
+    })
+}
+
+// The mnemonic text for an opcode, the inverse of `mnemonic_to_opcode`. Used
+// by `ProcessingUnit::disassemble`.
+pub(crate) fn opcode_to_mnemonic(opcode: Opcode) -> &'static str {
+    use Opcode::*;
+    match opcode {
+        Nop => "nop",
+        Add => "add",
+        Sub => "sub",
+        Mul => "mul",
+        Div => "div",
+        Store => "store",
+        Load => "load",
+        LoadImmediate => "li",
+        Push => "push",
+        Pop => "pop",
+        Jmp => "jmp",
+        Jz => "jz",
+        Jnz => "jnz",
+        Mov => "mov",
+        Je => "je",
+        Jne => "jne",
+        And => "and",
+        Or => "or",
+        Xor => "xor",
+        Not => "not",
+        Shl => "shl",
+        Shr => "shr",
+        Cmp => "cmp",
+        Test => "test",
+        B => "b",
+        Bz => "bz",
+        Bnz => "bnz",
+        Neg => "neg",
+        Abs => "abs",
+        Mod => "mod",
+        Inc => "inc",
+        Dec => "dec",
+        Halt => "halt",
+        Bcc => "bcc",
+        Bcs => "bcs",
+        Bge => "bge",
+        Blt => "blt",
+        Bgt => "bgt",
+        Ble => "ble",
+        AddU => "addu",
+        AddS => "adds",
+        AddF => "addf",
+        SubU => "subu",
+        SubS => "subs",
+        SubF => "subf",
+        MulU => "mulu",
+        MulS => "muls",
+        MulF => "mulf",
+        DivU => "divu",
+        DivS => "divs",
+        DivF => "divf",
+        ModU => "modu",
+        ModS => "mods",
+        ModF => "modf",
+        ShrU => "shru",
+    }
+}
+
+pub(crate) fn operand_shape(opcode: Opcode) -> Shape {
+    use Opcode::*;
+    match opcode {
+        Nop | Halt => Shape::None,
+        Push | Pop | Inc | Dec => Shape::Reg,
+        Mov | Not | Neg | Abs => Shape::RegReg,
+        Add | Sub | Mul | Div | Mod | And | Or | Xor | Shl | Shr | Cmp | Test | AddU | AddS
+        | AddF | SubU | SubS | SubF | MulU | MulS | MulF | DivU | DivS | DivF | ModU | ModS
+        | ModF | ShrU => Shape::RegRegReg,
+        Store | Load => Shape::RegAddr,
+        LoadImmediate => Shape::RegImm,
+        Jmp | B | Bcc | Bcs | Bge | Blt | Bgt | Ble => Shape::Label,
+        Jz | Jnz | Bz | Bnz => Shape::RegLabel,
+        Je | Jne => Shape::RegRegLabel,
+    }
+}
+
+fn parse_register(token: &str, line: usize) -> Result<usize, AsmError> {
+    let bad = || AsmError::InvalidOperand { line, token: token.to_string() };
+    let digits = token.strip_prefix('r').or_else(|| token.strip_prefix('R')).ok_or_else(bad)?;
+    digits.parse::<usize>().map_err(|_| bad())
+}
+
+fn parse_immediate(token: &str, line: usize) -> Result<i32, AsmError> {
+    token
+        .parse::<i32>()
+        .map_err(|_| AsmError::InvalidOperand { line, token: token.to_string() })
+}
+
+// Resolves an `addr`/label operand to a word address. A bare number is taken
+// as a literal word address; anything else must be a label defined somewhere
+// in the program, resolved to the word address of its instruction.
+fn resolve_addr(token: &str, line: usize, labels: &HashMap<String, usize>) -> Result<usize, AsmError> {
+    if let Ok(addr) = token.parse::<usize>() {
+        return Ok(addr);
+    }
+    labels
+        .get(token)
+        .map(|&index| index * isa::INSTRUCTION_WORDS)
+        .ok_or_else(|| AsmError::UndefinedLabel { line, label: token.to_string() })
+}
+
+struct RawInstruction {
+    line: usize,
+    mnemonic: String,
+    operands: Vec<String>,
+}
+
+fn build_instruction(
+    opcode: Opcode,
+    raw: &RawInstruction,
+    labels: &HashMap<String, usize>,
+) -> Result<Instruction, AsmError> {
+    let expect = |count: usize| -> Result<(), AsmError> {
+        if raw.operands.len() != count {
+            Err(AsmError::WrongOperandCount {
+                line: raw.line,
+                mnemonic: raw.mnemonic.clone(),
+                expected: count,
+                found: raw.operands.len(),
+            })
+        } else {
+            Ok(())
+        }
+    };
+
+    let mut instr = Instruction { opcode, reg1: 0, reg2: 0, reg3: 0, addr: 0, immediate: 0 };
+    match operand_shape(opcode) {
+        Shape::None => expect(0)?,
+        Shape::Reg => {
+            expect(1)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+        }
+        Shape::RegReg => {
+            expect(2)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+            instr.reg2 = parse_register(&raw.operands[1], raw.line)?;
+        }
+        Shape::RegRegReg => {
+            expect(3)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+            instr.reg2 = parse_register(&raw.operands[1], raw.line)?;
+            instr.reg3 = parse_register(&raw.operands[2], raw.line)?;
+        }
+        Shape::RegAddr => {
+            expect(2)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+            instr.addr = resolve_addr(&raw.operands[1], raw.line, labels)?;
+        }
+        Shape::RegImm => {
+            expect(2)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+            instr.immediate = parse_immediate(&raw.operands[1], raw.line)?;
+        }
+        Shape::Label => {
+            expect(1)?;
+            instr.addr = resolve_addr(&raw.operands[0], raw.line, labels)?;
+        }
+        Shape::RegLabel => {
+            expect(2)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+            instr.addr = resolve_addr(&raw.operands[1], raw.line, labels)?;
+        }
+        Shape::RegRegLabel => {
+            expect(3)?;
+            instr.reg1 = parse_register(&raw.operands[0], raw.line)?;
+            instr.reg2 = parse_register(&raw.operands[1], raw.line)?;
+            instr.addr = resolve_addr(&raw.operands[2], raw.line, labels)?;
+        }
+    }
+    Ok(instr)
+}
+
+// Assembles mnemonic source into a sequence of `Instruction`s. Labels
+// (`loop:`) are resolved to instruction addresses in a second pass, so
+// `Jmp`/`Jz`/`B`-family targets can be written as names rather than
+// hand-counted offsets.
+pub fn assemble(src: &str) -> Result<Vec<Instruction>, AsmError> {
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut raws: Vec<RawInstruction> = Vec::new();
+
+    for (offset, raw_line) in src.lines().enumerate() {
+        let line = offset + 1;
+        let without_comment = raw_line.split(';').next().unwrap_or("");
+        let mut rest = without_comment.trim();
+        if rest.is_empty() {
+            continue;
+        }
+
+        if let Some(colon) = rest.find(':') {
+            let label = rest[..colon].trim();
+            let is_label = !label.is_empty()
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if is_label {
+                if labels.contains_key(label) {
+                    return Err(AsmError::DuplicateLabel { line, label: label.to_string() });
+                }
+                labels.insert(label.to_string(), raws.len());
+                rest = rest[colon + 1..].trim();
+                if rest.is_empty() {
+                    continue;
+                }
+            }
+        }
+
+        let mut tokens = rest
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|tok| !tok.is_empty());
+        let mnemonic = tokens
+            .next()
+            .expect("non-empty line yields at least one token")
+            .to_lowercase();
+        let operands = tokens.map(|tok| tok.to_string()).collect();
+        raws.push(RawInstruction { line, mnemonic, operands });
+    }
+
+    raws.iter()
+        .map(|raw| {
+            let opcode = mnemonic_to_opcode(&raw.mnemonic).ok_or_else(|| AsmError::UnknownMnemonic {
+                line: raw.line,
+                mnemonic: raw.mnemonic.clone(),
+            })?;
+            build_instruction(opcode, raw, &labels)
+        })
+        .collect()
+}
@@ -0,0 +1,104 @@
+use std::io::{self, Read, Write};
+
+use crate::error::MdpuError;
+
+// A memory-mapped device. `offset` is relative to the start of the device's
+// registered address window, not the absolute bus address.
+pub trait Device {
+    fn read(&self, offset: usize) -> i32;
+    fn write(&mut self, offset: usize, val: i32);
+}
+
+// Writes to this device print the value as a character to stdout.
+pub struct StdoutDevice;
+
+impl Device for StdoutDevice {
+    fn read(&self, _offset: usize) -> i32 {
+        0
+    }
+
+    fn write(&mut self, _offset: usize, val: i32) {
+        if let Some(c) = char::from_u32(val as u32) {
+            print!("{}", c);
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+// Reads from this device pull one byte from stdin, returning -1 at EOF.
+pub struct StdinDevice;
+
+impl Device for StdinDevice {
+    fn read(&self, _offset: usize) -> i32 {
+        let mut byte = [0u8; 1];
+        match io::stdin().read_exact(&mut byte) {
+            Ok(()) => byte[0] as i32,
+            Err(_) => -1,
+        }
+    }
+
+    fn write(&mut self, _offset: usize, _val: i32) {}
+}
+
+struct MappedDevice {
+    start: usize,
+    end: usize,
+    device: Box<dyn Device>,
+}
+
+// Routes loads/stores either to flat RAM or to a registered `Device`, the way the
+// RISC-V core's bus routes `GPU_BASE` separately from RAM and the `hence`
+// emulator maps a terminal read/write at fixed addresses.
+pub struct Bus {
+    ram: Vec<i32>,
+    devices: Vec<MappedDevice>,
+}
+
+impl Bus {
+    pub fn new(ram_size: usize) -> Self {
+        Bus {
+            ram: vec![0; ram_size],
+            devices: Vec::new(),
+        }
+    }
+
+    // Registers `device` over the half-open address range `[start, end)`. The
+    // range must lie outside RAM; addresses below `ram_len()` always hit RAM.
+    pub fn register_device(&mut self, start: usize, end: usize, device: Box<dyn Device>) {
+        self.devices.push(MappedDevice { start, end, device });
+    }
+
+    pub fn ram_len(&self) -> usize {
+        self.ram.len()
+    }
+
+    pub fn ram(&self) -> &[i32] {
+        &self.ram
+    }
+
+    pub fn load(&self, addr: usize) -> Result<i32, MdpuError> {
+        if addr < self.ram.len() {
+            return Ok(self.ram[addr]);
+        }
+        for mapped in &self.devices {
+            if addr >= mapped.start && addr < mapped.end {
+                return Ok(mapped.device.read(addr - mapped.start));
+            }
+        }
+        Err(MdpuError::MemoryOutOfBounds(addr))
+    }
+
+    pub fn store(&mut self, addr: usize, val: i32) -> Result<(), MdpuError> {
+        if addr < self.ram.len() {
+            self.ram[addr] = val;
+            return Ok(());
+        }
+        for mapped in &mut self.devices {
+            if addr >= mapped.start && addr < mapped.end {
+                mapped.device.write(addr - mapped.start, val);
+                return Ok(());
+            }
+        }
+        Err(MdpuError::MemoryOutOfBounds(addr))
+    }
+}
@@ -0,0 +1,81 @@
+use crate::asm::{self, Shape};
+use crate::isa;
+use crate::ProcessingUnit;
+
+// The result of a single `step`, or of running until something interrupts
+// execution, mirroring the moa m68k core's decode/execute split and its
+// `Error::breakpoint` stop condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Continued,
+    Halted,
+    Breakpoint,
+}
+
+impl ProcessingUnit {
+    pub fn add_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.remove(&ip);
+    }
+
+    // Fetches, decodes, and executes exactly one instruction at the current
+    // `instruction_pointer`.
+    pub fn step(&mut self) -> Result<StepOutcome, crate::error::MdpuError> {
+        let instr = self.fetch_instruction(self.instruction_pointer)?;
+        let halted = self.execute_current(&instr)?;
+        Ok(if halted { StepOutcome::Halted } else { StepOutcome::Continued })
+    }
+
+    // Prints registers, stack pointer, and status flags so a paused program
+    // can be inspected between steps.
+    pub fn dump_state(&self) {
+        println!("IP: {}", self.instruction_pointer);
+        println!("Registers: {:?}", self.registers);
+        println!("Stack pointer: {}", self.stack_pointer);
+        println!(
+            "Flags: Z={} N={} C={} V={}",
+            self.status.zero as u8,
+            self.status.negative as u8,
+            self.status.carry as u8,
+            self.status.overflow as u8,
+        );
+    }
+
+    // Renders the loaded program as an aligned OFFSET / INSTRUCTION table.
+    // Bounded by `program_words` (set by `load_program`) rather than decode
+    // failure, since a zero byte decodes as a valid `Nop` and RAM beyond the
+    // program is zero-filled: left unbounded, this would walk all of RAM and,
+    // once past it, start faulting in MMIO devices (e.g. consuming stdin)
+    // even though this is meant to be a read-only introspection routine.
+    pub fn disassemble(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        while offset + isa::INSTRUCTION_WORDS <= self.program_words {
+            match self.fetch_instruction(offset) {
+                Ok(instr) => lines.push(format!("{:04}  {}", offset, format_instruction(&instr))),
+                Err(_) => break,
+            }
+            offset += isa::INSTRUCTION_WORDS;
+        }
+        lines
+    }
+}
+
+fn format_instruction(instr: &crate::Instruction) -> String {
+    let mnemonic = asm::opcode_to_mnemonic(instr.opcode);
+    let operands = match asm::operand_shape(instr.opcode) {
+        Shape::None => String::new(),
+        Shape::Reg => format!("r{}", instr.reg1),
+        Shape::RegReg => format!("r{}, r{}", instr.reg1, instr.reg2),
+        Shape::RegRegReg => format!("r{}, r{}, r{}", instr.reg1, instr.reg2, instr.reg3),
+        Shape::RegAddr => format!("r{}, {}", instr.reg1, instr.addr),
+        Shape::RegImm => format!("r{}, {}", instr.reg1, instr.immediate),
+        Shape::Label => format!("{}", instr.addr),
+        Shape::RegLabel => format!("r{}, {}", instr.reg1, instr.addr),
+        Shape::RegRegLabel => format!("r{}, r{}, {}", instr.reg1, instr.reg2, instr.addr),
+    };
+    format!("{:<6} {}", mnemonic, operands)
+}
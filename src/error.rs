@@ -0,0 +1,39 @@
+use std::fmt;
+
+// Faults raised by a `ProcessingUnit` while executing a program. Modeled after the
+// error subsystems in emulators like BurritOS and moa: every fault that used to
+// terminate the process is now a value the host can catch and react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MdpuError {
+    RegisterOutOfBounds(usize),
+    MemoryOutOfBounds(usize),
+    DivisionByZero { reg: usize, value: i32 },
+    StackOverflow,
+    StackUnderflow,
+    InstructionLimitExceeded,
+    InvalidOpcode(u8),
+}
+
+impl fmt::Display for MdpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MdpuError::RegisterOutOfBounds(reg) => {
+                write!(f, "register index out of bounds: R{}", reg)
+            }
+            MdpuError::MemoryOutOfBounds(addr) => {
+                write!(f, "memory address out of bounds: {}", addr)
+            }
+            MdpuError::DivisionByZero { reg, value } => {
+                write!(f, "division by zero on R{} of value {}", reg, value)
+            }
+            MdpuError::StackOverflow => write!(f, "stack overflow"),
+            MdpuError::StackUnderflow => write!(f, "stack underflow"),
+            MdpuError::InstructionLimitExceeded => {
+                write!(f, "maximum instruction count exceeded, possible infinite loop")
+            }
+            MdpuError::InvalidOpcode(byte) => write!(f, "invalid opcode byte: {:#04x}", byte),
+        }
+    }
+}
+
+impl std::error::Error for MdpuError {}
@@ -0,0 +1,101 @@
+use crate::error::MdpuError;
+use crate::{Instruction, Opcode};
+
+// Fixed-width binary encoding for an `Instruction`: one opcode byte, three
+// register-index bytes, a big-endian `addr` word, and a big-endian `immediate`
+// word. Every instruction occupies the same number of bytes so the fetch loop
+// can step through memory without a length table.
+pub const INSTRUCTION_BYTES: usize = 12;
+pub const INSTRUCTION_WORDS: usize = INSTRUCTION_BYTES / 4;
+
+impl Opcode {
+    fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, MdpuError> {
+        match byte {
+            0 => Ok(Opcode::Nop),
+            1 => Ok(Opcode::Add),
+            2 => Ok(Opcode::Sub),
+            3 => Ok(Opcode::Mul),
+            4 => Ok(Opcode::Div),
+            5 => Ok(Opcode::Store),
+            6 => Ok(Opcode::Load),
+            7 => Ok(Opcode::LoadImmediate),
+            8 => Ok(Opcode::Push),
+            9 => Ok(Opcode::Pop),
+            10 => Ok(Opcode::Jmp),
+            11 => Ok(Opcode::Jz),
+            12 => Ok(Opcode::Jnz),
+            13 => Ok(Opcode::Mov),
+            14 => Ok(Opcode::Je),
+            15 => Ok(Opcode::Jne),
+            16 => Ok(Opcode::And),
+            17 => Ok(Opcode::Or),
+            18 => Ok(Opcode::Xor),
+            19 => Ok(Opcode::Not),
+            20 => Ok(Opcode::Shl),
+            21 => Ok(Opcode::Shr),
+            22 => Ok(Opcode::Cmp),
+            23 => Ok(Opcode::Test),
+            24 => Ok(Opcode::B),
+            25 => Ok(Opcode::Bz),
+            26 => Ok(Opcode::Bnz),
+            27 => Ok(Opcode::Neg),
+            28 => Ok(Opcode::Abs),
+            29 => Ok(Opcode::Mod),
+            30 => Ok(Opcode::Inc),
+            31 => Ok(Opcode::Dec),
+            32 => Ok(Opcode::Halt),
+            33 => Ok(Opcode::Bcc),
+            34 => Ok(Opcode::Bcs),
+            35 => Ok(Opcode::Bge),
+            36 => Ok(Opcode::Blt),
+            37 => Ok(Opcode::Bgt),
+            38 => Ok(Opcode::Ble),
+            39 => Ok(Opcode::AddU),
+            40 => Ok(Opcode::AddS),
+            41 => Ok(Opcode::AddF),
+            42 => Ok(Opcode::SubU),
+            43 => Ok(Opcode::SubS),
+            44 => Ok(Opcode::SubF),
+            45 => Ok(Opcode::MulU),
+            46 => Ok(Opcode::MulS),
+            47 => Ok(Opcode::MulF),
+            48 => Ok(Opcode::DivU),
+            49 => Ok(Opcode::DivS),
+            50 => Ok(Opcode::DivF),
+            51 => Ok(Opcode::ModU),
+            52 => Ok(Opcode::ModS),
+            53 => Ok(Opcode::ModF),
+            54 => Ok(Opcode::ShrU),
+            other => Err(MdpuError::InvalidOpcode(other)),
+        }
+    }
+}
+
+pub fn encode(instr: &Instruction) -> [u8; INSTRUCTION_BYTES] {
+    let mut bytes = [0u8; INSTRUCTION_BYTES];
+    bytes[0] = instr.opcode.to_byte();
+    bytes[1] = instr.reg1 as u8;
+    bytes[2] = instr.reg2 as u8;
+    bytes[3] = instr.reg3 as u8;
+    bytes[4..8].copy_from_slice(&(instr.addr as u32).to_be_bytes());
+    bytes[8..12].copy_from_slice(&instr.immediate.to_be_bytes());
+    bytes
+}
+
+pub fn decode(bytes: &[u8; INSTRUCTION_BYTES]) -> Result<Instruction, MdpuError> {
+    let opcode = Opcode::from_byte(bytes[0])?;
+    let addr = u32::from_be_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    let immediate = i32::from_be_bytes(bytes[8..12].try_into().unwrap());
+    Ok(Instruction {
+        opcode,
+        reg1: bytes[1] as usize,
+        reg2: bytes[2] as usize,
+        reg3: bytes[3] as usize,
+        addr,
+        immediate,
+    })
+}
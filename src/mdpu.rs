@@ -1,11 +1,28 @@
-use std::io::{self, Write};
-use std::mem;
+mod asm;
+mod bus;
+mod debugger;
+mod error;
+mod isa;
+mod status;
+
+use std::collections::HashSet;
+
+use bus::Bus;
+use error::MdpuError;
+use status::Status;
 
 // Define the structure of the multi-dimensional processing unit
 struct ProcessingUnit {
     registers: Vec<i32>,
-    memory: Vec<i32>,
+    memory: Bus,
     stack_pointer: usize,
+    status: Status,
+    instruction_pointer: usize,
+    breakpoints: HashSet<usize>,
+    // Word length of the most recently loaded program, so introspection like
+    // `disassemble` knows where real code ends instead of reading into
+    // zero-filled RAM or the MMIO window above it.
+    program_words: usize,
 }
 
 // Define the structure to hold the state after execution
@@ -14,42 +31,70 @@ struct ProcessingUnitState {
     stack: Vec<i32>,
 }
 
-// Define opcodes
+// Define opcodes. Explicit discriminants give each opcode a stable byte value
+// for the binary encoding in `isa`.
 #[derive(Debug, Copy, Clone)]
 enum Opcode {
-    Nop,
-    Add,
-    Sub,
-    Mul,
-    Div,
-    Store,
-    Load,
-    LoadImmediate,
-    Push,
-    Pop,
-    Jmp,
-    Jz,
-    Jnz,
-    Mov,
-    Je,
-    Jne,
-    And,
-    Or,
-    Xor,
-    Not,
-    Shl,
-    Shr,
-    Cmp,
-    Test,
-    B,
-    Bz,
-    Bnz,
-    Neg,
-    Abs,
-    Mod,
-    Inc,
-    Dec,
-    Halt,
+    Nop = 0,
+    Add = 1,
+    Sub = 2,
+    Mul = 3,
+    Div = 4,
+    Store = 5,
+    Load = 6,
+    LoadImmediate = 7,
+    Push = 8,
+    Pop = 9,
+    Jmp = 10,
+    Jz = 11,
+    Jnz = 12,
+    Mov = 13,
+    Je = 14,
+    Jne = 15,
+    And = 16,
+    Or = 17,
+    Xor = 18,
+    Not = 19,
+    Shl = 20,
+    Shr = 21,
+    Cmp = 22,
+    Test = 23,
+    B = 24,
+    Bz = 25,
+    Bnz = 26,
+    Neg = 27,
+    Abs = 28,
+    Mod = 29,
+    Inc = 30,
+    Dec = 31,
+    Halt = 32,
+    // Flag-based branches: consult the status register set by `Cmp`/`Test`
+    // instead of re-reading a register.
+    Bcc = 33,
+    Bcs = 34,
+    Bge = 35,
+    Blt = 36,
+    Bgt = 37,
+    Ble = 38,
+    // Typed arithmetic: the `U`/`S`/`F` suffix picks how the register bits are
+    // reinterpreted before the operation (unsigned `u32`, wrapping signed
+    // `i32`, or `f32` bit pattern), mirroring the holey-bytes `MATH_TYPE` split.
+    AddU = 39,
+    AddS = 40,
+    AddF = 41,
+    SubU = 42,
+    SubS = 43,
+    SubF = 44,
+    MulU = 45,
+    MulS = 46,
+    MulF = 47,
+    DivU = 48,
+    DivS = 49,
+    DivF = 50,
+    ModU = 51,
+    ModS = 52,
+    ModF = 53,
+    ShrU = 54,
 }
 
 // Define the structure of an instruction
@@ -67,331 +112,632 @@ impl ProcessingUnit {
     fn initialize(num_registers: usize, memory_size: usize) -> Self {
         ProcessingUnit {
             registers: vec![0; num_registers],
-            memory: vec![0; memory_size],
+            memory: Bus::new(memory_size),
             stack_pointer: memory_size - 1, // Initialize stack pointer to the top of the memory
+            status: Status::new(),
+            instruction_pointer: 0,
+            breakpoints: HashSet::new(),
+            program_words: 0,
+        }
+    }
+
+    // Maps a device onto the address range `[start, end)`, above RAM.
+    fn attach_device(&mut self, start: usize, end: usize, device: Box<dyn bus::Device>) {
+        self.memory.register_device(start, end, device);
+    }
+
+    // Fills memory from a flat byte buffer of encoded instructions, starting at
+    // address 0, so a program can be assembled once and loaded as bytecode
+    // rather than built as `Instruction` literals.
+    fn load_program(&mut self, bytes: &[u8]) -> Result<(), MdpuError> {
+        let mut word_count = 0;
+        for (word_index, chunk) in bytes.chunks(4).enumerate() {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.memory.store(word_index, i32::from_be_bytes(word))?;
+            word_count = word_index + 1;
         }
+        self.program_words = word_count;
+        Ok(())
+    }
+
+    // Reads one fixed-width encoded instruction out of memory at `ip` (a word
+    // address) and decodes it.
+    fn fetch_instruction(&self, ip: usize) -> Result<Instruction, MdpuError> {
+        let mut bytes = [0u8; isa::INSTRUCTION_BYTES];
+        for word in 0..isa::INSTRUCTION_WORDS {
+            let value = self.memory.load(ip + word)?;
+            bytes[word * 4..word * 4 + 4].copy_from_slice(&value.to_be_bytes());
+        }
+        isa::decode(&bytes)
     }
 
     // Helper function to check register bounds
-    fn check_register_bounds(&self, reg: usize) {
+    fn check_register_bounds(&self, reg: usize) -> Result<(), MdpuError> {
         if reg >= self.registers.len() {
-            eprintln!("Error: Register index out of bounds: R{}", reg);
-            std::process::exit(1);
+            Err(MdpuError::RegisterOutOfBounds(reg))
+        } else {
+            Ok(())
         }
     }
 
     // ++++++++++++++++++++++++++++++ Arithmetic operations ++++++++++++++++++++++++++++++ //
-    fn add(&mut self, reg1: usize, reg2: usize, reg3: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
-        self.check_register_bounds(reg3);
+    fn add(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
         self.registers[reg3] = self.registers[reg1] + self.registers[reg2];
+        Ok(())
     }
 
-    fn subtract(&mut self, reg1: usize, reg2: usize, reg3: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
-        self.check_register_bounds(reg3);
+    fn subtract(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
         self.registers[reg3] = self.registers[reg1] - self.registers[reg2];
+        Ok(())
     }
 
-    fn multiply(&mut self, reg1: usize, reg2: usize, reg3: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
-        self.check_register_bounds(reg3);
+    fn multiply(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
         self.registers[reg3] = self.registers[reg1] * self.registers[reg2];
+        Ok(())
     }
 
-    fn divide(&mut self, reg1: usize, reg2: usize, reg3: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
-        self.check_register_bounds(reg3);
+    fn divide(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
         if self.registers[reg2] != 0 {
             self.registers[reg3] = self.registers[reg1] / self.registers[reg2];
+            Ok(())
         } else {
-            eprintln!(
-                "Error: Division by zero on R{} of value {}",
-                reg2, self.registers[reg2]
-            );
-            std::process::exit(1);
+            Err(MdpuError::DivisionByZero {
+                reg: reg2,
+                value: self.registers[reg2],
+            })
         }
     }
 
-    fn neg(&mut self, reg1: usize, reg2: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
+    fn neg(&mut self, reg1: usize, reg2: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
         self.registers[reg2] = -self.registers[reg1];
+        Ok(())
     }
 
-    fn absolute(&mut self, reg1: usize, reg2: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
+    fn absolute(&mut self, reg1: usize, reg2: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
         self.registers[reg2] = self.registers[reg1].abs();
+        Ok(())
     }
 
-    fn mod_op(&mut self, reg1: usize, reg2: usize, reg3: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
-        self.check_register_bounds(reg3);
+    fn mod_op(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
         if self.registers[reg2] != 0 {
             self.registers[reg3] = self.registers[reg1] % self.registers[reg2];
+            Ok(())
         } else {
-            eprintln!(
-                "Error: Division by zero on R{} of value {}",
-                reg2, self.registers[reg2]
-            );
-            std::process::exit(1);
+            Err(MdpuError::DivisionByZero {
+                reg: reg2,
+                value: self.registers[reg2],
+            })
         }
     }
 
-    // ++++++++++++++++++++++++++++++ Memory operations ++++++++++++++++++++++++++++++ //
-    fn store(&mut self, reg: usize, addr: usize) {
-        self.check_register_bounds(reg);
-        if addr < self.memory.len() {
-            self.memory[addr] = self.registers[reg];
-        } else {
-            eprintln!("Error: Memory address out of bounds: {}", addr);
-            std::process::exit(1);
+    // ++++++++++++++++++++++++++++++ Typed arithmetic ++++++++++++++++++++++++++++++ //
+    // Unsigned variants reinterpret the register bits as `u32` and wrap on
+    // overflow, instead of relying on debug-mode panics.
+    fn add_u(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = (self.registers[reg1] as u32).wrapping_add(self.registers[reg2] as u32);
+        self.registers[reg3] = result as i32;
+        Ok(())
+    }
+
+    fn sub_u(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = (self.registers[reg1] as u32).wrapping_sub(self.registers[reg2] as u32);
+        self.registers[reg3] = result as i32;
+        Ok(())
+    }
+
+    fn mul_u(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = (self.registers[reg1] as u32).wrapping_mul(self.registers[reg2] as u32);
+        self.registers[reg3] = result as i32;
+        Ok(())
+    }
+
+    fn div_u(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let divisor = self.registers[reg2] as u32;
+        if divisor == 0 {
+            return Err(MdpuError::DivisionByZero {
+                reg: reg2,
+                value: self.registers[reg2],
+            });
         }
+        self.registers[reg3] = ((self.registers[reg1] as u32) / divisor) as i32;
+        Ok(())
     }
 
-    fn load(&mut self, addr: usize, reg: usize) {
-        self.check_register_bounds(reg);
-        if addr < self.memory.len() {
-            self.registers[reg] = self.memory[addr];
-        } else {
-            eprintln!("Error: Memory address out of bounds: {}", addr);
-            std::process::exit(1);
+    fn mod_u(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let divisor = self.registers[reg2] as u32;
+        if divisor == 0 {
+            return Err(MdpuError::DivisionByZero {
+                reg: reg2,
+                value: self.registers[reg2],
+            });
+        }
+        self.registers[reg3] = ((self.registers[reg1] as u32) % divisor) as i32;
+        Ok(())
+    }
+
+    fn shr_u(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = (self.registers[reg1] as u32) >> (self.registers[reg2] as u32);
+        self.registers[reg3] = result as i32;
+        Ok(())
+    }
+
+    // Signed variants are explicit about wrapping on overflow, unlike the
+    // plain `Add`/`Sub`/`Mul`/`Div`/`Mod` ops above which use native `i32` math.
+    fn add_s(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        self.registers[reg3] = self.registers[reg1].wrapping_add(self.registers[reg2]);
+        Ok(())
+    }
+
+    fn sub_s(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        self.registers[reg3] = self.registers[reg1].wrapping_sub(self.registers[reg2]);
+        Ok(())
+    }
+
+    fn mul_s(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        self.registers[reg3] = self.registers[reg1].wrapping_mul(self.registers[reg2]);
+        Ok(())
+    }
+
+    fn div_s(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        if self.registers[reg2] == 0 {
+            return Err(MdpuError::DivisionByZero {
+                reg: reg2,
+                value: self.registers[reg2],
+            });
         }
+        self.registers[reg3] = self.registers[reg1].wrapping_div(self.registers[reg2]);
+        Ok(())
+    }
+
+    fn mod_s(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        if self.registers[reg2] == 0 {
+            return Err(MdpuError::DivisionByZero {
+                reg: reg2,
+                value: self.registers[reg2],
+            });
+        }
+        self.registers[reg3] = self.registers[reg1].wrapping_rem(self.registers[reg2]);
+        Ok(())
+    }
+
+    // Floating-point variants reinterpret the register's `i32` bit pattern as
+    // `f32` (there is no separate float register bank).
+    fn add_f(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = self.reg_as_f32(reg1) + self.reg_as_f32(reg2);
+        self.registers[reg3] = result.to_bits() as i32;
+        Ok(())
+    }
+
+    fn sub_f(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = self.reg_as_f32(reg1) - self.reg_as_f32(reg2);
+        self.registers[reg3] = result.to_bits() as i32;
+        Ok(())
+    }
+
+    fn mul_f(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = self.reg_as_f32(reg1) * self.reg_as_f32(reg2);
+        self.registers[reg3] = result.to_bits() as i32;
+        Ok(())
+    }
+
+    fn div_f(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = self.reg_as_f32(reg1) / self.reg_as_f32(reg2);
+        self.registers[reg3] = result.to_bits() as i32;
+        Ok(())
+    }
+
+    fn mod_f(&mut self, reg1: usize, reg2: usize, reg3: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
+        self.check_register_bounds(reg3)?;
+        let result = self.reg_as_f32(reg1) % self.reg_as_f32(reg2);
+        self.registers[reg3] = result.to_bits() as i32;
+        Ok(())
+    }
+
+    fn reg_as_f32(&self, reg: usize) -> f32 {
+        f32::from_bits(self.registers[reg] as u32)
+    }
+
+    // ++++++++++++++++++++++++++++++ Memory operations ++++++++++++++++++++++++++++++ //
+    fn store(&mut self, reg: usize, addr: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg)?;
+        self.memory.store(addr, self.registers[reg])
+    }
+
+    fn load(&mut self, addr: usize, reg: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg)?;
+        self.registers[reg] = self.memory.load(addr)?;
+        Ok(())
     }
 
     // ++++++++++++++++++++++++++++++ Stack operations ++++++++++++++++++++++++++++++ //
-    fn push(&mut self, reg: usize) {
-        self.check_register_bounds(reg);
+    fn push(&mut self, reg: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg)?;
         if self.stack_pointer > 0 {
-            self.memory[self.stack_pointer] = self.registers[reg];
+            self.memory.store(self.stack_pointer, self.registers[reg])?;
             self.stack_pointer -= 1;
+            Ok(())
         } else {
-            eprintln!("Error: Stack overflow on R{}", reg);
-            std::process::exit(1);
+            Err(MdpuError::StackOverflow)
         }
     }
 
-    fn pop(&mut self, reg: usize) {
-        self.check_register_bounds(reg);
-        if self.stack_pointer < self.memory.len() - 1 {
+    fn pop(&mut self, reg: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg)?;
+        if self.stack_pointer < self.memory.ram_len() - 1 {
             self.stack_pointer += 1;
-            self.registers[reg] = self.memory[self.stack_pointer];
+            self.registers[reg] = self.memory.load(self.stack_pointer)?;
+            Ok(())
         } else {
-            eprintln!("Error: Stack underflow on R{}", reg);
-            std::process::exit(1);
+            Err(MdpuError::StackUnderflow)
         }
     }
 
-    fn mov(&mut self, reg1: usize, reg2: usize) {
-        self.check_register_bounds(reg1);
-        self.check_register_bounds(reg2);
+    fn mov(&mut self, reg1: usize, reg2: usize) -> Result<(), MdpuError> {
+        self.check_register_bounds(reg1)?;
+        self.check_register_bounds(reg2)?;
         self.registers[reg1] = self.registers[reg2];
+        Ok(())
     }
-}
-
-// Function to run the program and return the state
-fn run(pu: &mut ProcessingUnit, program: &[Instruction], mic: usize) -> ProcessingUnitState {
-    execute_program(pu, program, mic);
-    let stack_size = pu.memory.len() - pu.stack_pointer - 1;
-
-    let stack = pu.memory[pu.stack_pointer + 1..].to_vec();
-    let registers = pu.registers.clone();
 
-    ProcessingUnitState { registers, stack }
-}
-
-// ++++++++++++++++++++++++++++++ Program execution ++++++++++++++++++++++++++++++ //
-fn execute_program(pu: &mut ProcessingUnit, program: &[Instruction], mic: usize) {
-    let max_instruction_count = mic;
-    let mut instruction_count = 0;
-    let mut instruction_pointer = 0;
+    // ++++++++++++++++++++++++++++++ Single-instruction execution ++++++++++++++++++++++++++++++ //
+    // Applies one already-decoded instruction, advancing `instruction_pointer`
+    // (either to the branch target or past the instruction). Returns `Ok(true)`
+    // if the instruction was `Halt`.
+    fn execute_current(&mut self, instr: &Instruction) -> Result<bool, MdpuError> {
+        let mut jumped = false;
 
-    while instruction_pointer < program.len() {
-        if instruction_count >= max_instruction_count {
-            eprintln!("Error: Maximum instruction count exceeded, possible infinite loop");
-            std::process::exit(1);
-        }
-
-        let instr = &program[instruction_pointer];
         match instr.opcode {
-            Opcode::Add => pu.add(instr.reg1, instr.reg2, instr.reg3),
-            Opcode::Sub => pu.subtract(instr.reg1, instr.reg2, instr.reg3),
-            Opcode::Mul => pu.multiply(instr.reg1, instr.reg2, instr.reg3),
-            Opcode::Div => pu.divide(instr.reg1, instr.reg2, instr.reg3),
-            Opcode::Store => pu.store(instr.reg1, instr.addr),
-            Opcode::Load => pu.load(instr.addr, instr.reg1),
+            Opcode::Add => self.add(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::Sub => self.subtract(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::Mul => self.multiply(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::Div => self.divide(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::Store => self.store(instr.reg1, instr.addr)?,
+            Opcode::Load => self.load(instr.addr, instr.reg1)?,
             Opcode::LoadImmediate => {
-                pu.check_register_bounds(instr.reg1);
-                pu.registers[instr.reg1] = instr.immediate;
+                self.check_register_bounds(instr.reg1)?;
+                self.registers[instr.reg1] = instr.immediate;
             }
-            Opcode::Push => pu.push(instr.reg1),
-            Opcode::Pop => pu.pop(instr.reg1),
+            Opcode::Push => self.push(instr.reg1)?,
+            Opcode::Pop => self.pop(instr.reg1)?,
             Opcode::Jmp => {
-                instruction_pointer = instr.addr;
-                continue;
+                self.instruction_pointer = instr.addr;
+                jumped = true;
             }
             Opcode::Jz => {
-                pu.check_register_bounds(instr.reg1);
-                if pu.registers[instr.reg1] == 0 {
-                    instruction_pointer = instr.addr;
-                    continue;
+                self.check_register_bounds(instr.reg1)?;
+                if self.registers[instr.reg1] == 0 {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
                 }
             }
             Opcode::Jnz => {
-                pu.check_register_bounds(instr.reg1);
-                if pu.registers[instr.reg1] != 0 {
-                    instruction_pointer = instr.addr;
-                    continue;
+                self.check_register_bounds(instr.reg1)?;
+                if self.registers[instr.reg1] != 0 {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
                 }
             }
-            Opcode::Mov => pu.mov(instr.reg1, instr.reg2),
+            Opcode::Mov => self.mov(instr.reg1, instr.reg2)?,
             Opcode::Je => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                if pu.registers[instr.reg1] == pu.registers[instr.reg2] {
-                    instruction_pointer = instr.addr;
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                if self.registers[instr.reg1] == self.registers[instr.reg2] {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
                 }
             }
             Opcode::Jne => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                if pu.registers[instr.reg1] != pu.registers[instr.reg2] {
-                    instruction_pointer = instr.addr;
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                if self.registers[instr.reg1] != self.registers[instr.reg2] {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
                 }
             }
             Opcode::And => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = pu.registers[instr.reg1] & pu.registers[instr.reg2];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                self.registers[instr.reg3] = self.registers[instr.reg1] & self.registers[instr.reg2];
             }
             Opcode::Or => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = pu.registers[instr.reg1] | pu.registers[instr.reg2];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                self.registers[instr.reg3] = self.registers[instr.reg1] | self.registers[instr.reg2];
             }
             Opcode::Xor => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = pu.registers[instr.reg1] ^ pu.registers[instr.reg2];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                self.registers[instr.reg3] = self.registers[instr.reg1] ^ self.registers[instr.reg2];
             }
             Opcode::Not => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.registers[instr.reg2] = !pu.registers[instr.reg1];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.registers[instr.reg2] = !self.registers[instr.reg1];
             }
             Opcode::Shl => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = pu.registers[instr.reg1] << pu.registers[instr.reg2];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                self.registers[instr.reg3] = self.registers[instr.reg1] << self.registers[instr.reg2];
             }
             Opcode::Shr => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = pu.registers[instr.reg1] >> pu.registers[instr.reg2];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                self.registers[instr.reg3] = self.registers[instr.reg1] >> self.registers[instr.reg2];
             }
             Opcode::Cmp => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = (pu.registers[instr.reg1] - pu.registers[instr.reg2]);
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                let (a, b) = (self.registers[instr.reg1], self.registers[instr.reg2]);
+                self.status.set_from_sub(a, b);
+                self.registers[instr.reg3] = a - b;
             }
             Opcode::Test => {
-                pu.check_register_bounds(instr.reg1);
-                pu.check_register_bounds(instr.reg2);
-                pu.check_register_bounds(instr.reg3);
-                pu.registers[instr.reg3] = pu.registers[instr.reg1] & pu.registers[instr.reg2];
+                self.check_register_bounds(instr.reg1)?;
+                self.check_register_bounds(instr.reg2)?;
+                self.check_register_bounds(instr.reg3)?;
+                let (a, b) = (self.registers[instr.reg1], self.registers[instr.reg2]);
+                self.status.set_from_test(a, b);
+                self.registers[instr.reg3] = a & b;
             }
             Opcode::B => {
-                instruction_pointer = instr.addr;
-                continue;
+                self.instruction_pointer = instr.addr;
+                jumped = true;
             }
             Opcode::Bz => {
-                pu.check_register_bounds(instr.reg1);
-                if pu.registers[instr.reg1] == 0 {
-                    instruction_pointer = instr.addr;
-                    continue;
+                self.check_register_bounds(instr.reg1)?;
+                if self.registers[instr.reg1] == 0 {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
                 }
             }
             Opcode::Bnz => {
-                pu.check_register_bounds(instr.reg1);
-                if pu.registers[instr.reg1] != 0 {
-                    instruction_pointer = instr.addr;
-                    continue;
+                self.check_register_bounds(instr.reg1)?;
+                if self.registers[instr.reg1] != 0 {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
                 }
             }
-            Opcode::Neg => pu.neg(instr.reg1, instr.reg2),
-            Opcode::Abs => pu.absolute(instr.reg1, instr.reg2),
-            Opcode::Mod => pu.mod_op(instr.reg1, instr.reg2, instr.reg3),
+            Opcode::Neg => self.neg(instr.reg1, instr.reg2)?,
+            Opcode::Abs => self.absolute(instr.reg1, instr.reg2)?,
+            Opcode::Mod => self.mod_op(instr.reg1, instr.reg2, instr.reg3)?,
             Opcode::Inc => {
-                pu.check_register_bounds(instr.reg1);
-                pu.registers[instr.reg1] += 1;
+                self.check_register_bounds(instr.reg1)?;
+                self.registers[instr.reg1] += 1;
             }
             Opcode::Dec => {
-                pu.check_register_bounds(instr.reg1);
-                pu.registers[instr.reg1] -= 1;
+                self.check_register_bounds(instr.reg1)?;
+                self.registers[instr.reg1] -= 1;
+            }
+            Opcode::Bcc => {
+                if !self.status.carry {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
+                }
+            }
+            Opcode::Bcs => {
+                if self.status.carry {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
+                }
             }
+            Opcode::Bge => {
+                if self.status.ge() {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
+                }
+            }
+            Opcode::Blt => {
+                if self.status.lt() {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
+                }
+            }
+            Opcode::Bgt => {
+                if self.status.gt() {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
+                }
+            }
+            Opcode::Ble => {
+                if self.status.le() {
+                    self.instruction_pointer = instr.addr;
+                    jumped = true;
+                }
+            }
+            Opcode::AddU => self.add_u(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::AddS => self.add_s(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::AddF => self.add_f(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::SubU => self.sub_u(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::SubS => self.sub_s(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::SubF => self.sub_f(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::MulU => self.mul_u(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::MulS => self.mul_s(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::MulF => self.mul_f(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::DivU => self.div_u(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::DivS => self.div_s(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::DivF => self.div_f(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::ModU => self.mod_u(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::ModS => self.mod_s(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::ModF => self.mod_f(instr.reg1, instr.reg2, instr.reg3)?,
+            Opcode::ShrU => self.shr_u(instr.reg1, instr.reg2, instr.reg3)?,
             Opcode::Nop => {} // No operation
-            Opcode::Halt => break, // Stop execution
+            Opcode::Halt => return Ok(true),
+        }
+
+        if !jumped {
+            self.instruction_pointer += isa::INSTRUCTION_WORDS;
         }
+        Ok(false)
+    }
+}
+
+// Runs the loaded program until it halts or reaches a breakpoint and returns
+// the final state. Exceeding `mic` instructions returns
+// `MdpuError::InstructionLimitExceeded` instead of a state.
+fn run(pu: &mut ProcessingUnit, mic: usize) -> Result<(ProcessingUnitState, debugger::StepOutcome), MdpuError> {
+    let outcome = execute_program(pu, mic)?;
+    let stack = pu.memory.ram()[pu.stack_pointer + 1..].to_vec();
+    let registers = pu.registers.clone();
 
+    Ok((ProcessingUnitState { registers, stack }, outcome))
+}
+
+// ++++++++++++++++++++++++++++++ Program execution ++++++++++++++++++++++++++++++ //
+// Drives `ProcessingUnit::step` in a loop, stopping on `Halt`, on a
+// breakpoint, or once `mic` instructions have executed.
+fn execute_program(pu: &mut ProcessingUnit, mic: usize) -> Result<debugger::StepOutcome, MdpuError> {
+    let mut instruction_count = 0;
+
+    loop {
+        if pu.breakpoints.contains(&pu.instruction_pointer) {
+            return Ok(debugger::StepOutcome::Breakpoint);
+        }
+        if instruction_count >= mic {
+            return Err(MdpuError::InstructionLimitExceeded);
+        }
+
+        if pu.step()? == debugger::StepOutcome::Halted {
+            return Ok(debugger::StepOutcome::Halted);
+        }
         instruction_count += 1;
-        instruction_pointer += 1;
     }
 }
 
+
+// Memory-mapped console device addresses, just above the end of RAM.
+const CONSOLE_OUT_ADDR: usize = 128;
+const CONSOLE_IN_ADDR: usize = 129;
+
 fn main() {
     let mut pu = ProcessingUnit::initialize(8, 128);
+    pu.attach_device(CONSOLE_OUT_ADDR, CONSOLE_OUT_ADDR + 1, Box::new(bus::StdoutDevice));
+    pu.attach_device(CONSOLE_IN_ADDR, CONSOLE_IN_ADDR + 1, Box::new(bus::StdinDevice));
 
-    // Sample program instructions
-    let program = vec![
-        Instruction {
-            opcode: Opcode::LoadImmediate,
-            reg1: 0,
-            reg2: 0,
-            reg3: 0,
-            addr: 0,
-            immediate: 10,
-        },
-        Instruction {
-            opcode: Opcode::LoadImmediate,
-            reg1: 1,
-            reg2: 0,
-            reg3: 0,
-            addr: 0,
-            immediate: 20,
-        },
-        Instruction {
-            opcode: Opcode::Add,
-            reg1: 0,
-            reg2: 1,
-            reg3: 2,
-            addr: 0,
-            immediate: 0,
-        },
-        Instruction {
-            opcode: Opcode::Halt,
-            reg1: 0,
-            reg2: 0,
-            reg3: 0,
-            addr: 0,
-            immediate: 0,
-        },
-    ];
+    // Sample program, written as assembly rather than `Instruction` literals
+    let source = "\
+        li r0, 10\n\
+        li r1, 20\n\
+        add r0, r1, r2\n\
+        halt\n\
+    ";
+    let program = match asm::assemble(source) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Assembly error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let mic = 1000; // Maximum instruction count
-    let state = run(&mut pu, &program, mic);
+    let bytes: Vec<u8> = program.iter().flat_map(isa::encode).collect();
 
-    println!("Registers: {:?}", state.registers);
-    println!("Stack: {:?}", state.stack);
+    if let Err(e) = pu.load_program(&bytes) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    for line in pu.disassemble() {
+        println!("{}", line);
+    }
+
+    // Break just before the `add`, so we can inspect R0/R1 before they're combined.
+    pu.add_breakpoint(2 * isa::INSTRUCTION_WORDS);
+
+    let mic = 1000; // Maximum instruction count
+    match run(&mut pu, mic) {
+        Ok((_, debugger::StepOutcome::Breakpoint)) => {
+            pu.dump_state();
+            pu.remove_breakpoint(2 * isa::INSTRUCTION_WORDS);
+            match run(&mut pu, mic) {
+                Ok((state, _)) => {
+                    println!("Registers: {:?}", state.registers);
+                    println!("Stack: {:?}", state.stack);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Ok((state, _)) => {
+            println!("Registers: {:?}", state.registers);
+            println!("Stack: {:?}", state.stack);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
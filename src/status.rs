@@ -0,0 +1,58 @@
+// The processor status register: condition flags set by `Cmp`/`Test` and
+// consulted by the `Bcc`/`Bcs`/`Bge`/`Blt`/`Bgt`/`Ble` branch opcodes, the way
+// the m68k core's `Flags`/`Status`/`Condition` model keeps compare results out
+// of the general-purpose registers.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub zero: bool,
+    pub negative: bool,
+    pub carry: bool,
+    pub overflow: bool,
+}
+
+impl Status {
+    pub fn new() -> Self {
+        Status::default()
+    }
+
+    // Updates the flags for `a - b`, as used by `Cmp`. Carry is the unsigned
+    // borrow (reinterpreting both operands as `u32`); overflow is the signed
+    // overflow of the subtraction.
+    pub fn set_from_sub(&mut self, a: i32, b: i32) {
+        let (result, overflow) = a.overflowing_sub(b);
+        self.zero = result == 0;
+        self.negative = result < 0;
+        self.overflow = overflow;
+        self.carry = (a as u32).checked_sub(b as u32).is_none();
+    }
+
+    // Updates the flags for `a & b`, as used by `Test`. Bitwise ops clear
+    // carry and overflow.
+    pub fn set_from_test(&mut self, a: i32, b: i32) {
+        let result = a & b;
+        self.zero = result == 0;
+        self.negative = result < 0;
+        self.carry = false;
+        self.overflow = false;
+    }
+
+    // Signed greater-or-equal: N == V.
+    pub fn ge(&self) -> bool {
+        self.negative == self.overflow
+    }
+
+    // Signed less-than: N != V.
+    pub fn lt(&self) -> bool {
+        self.negative != self.overflow
+    }
+
+    // Signed greater-than: Z == 0 && N == V.
+    pub fn gt(&self) -> bool {
+        !self.zero && self.ge()
+    }
+
+    // Signed less-or-equal: Z == 1 || N != V.
+    pub fn le(&self) -> bool {
+        self.zero || self.lt()
+    }
+}